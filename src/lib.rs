@@ -5,7 +5,8 @@ extern crate delegate;
 
 use std::{
     collections::VecDeque,
-    iter::Extend,
+    iter::{Extend, FromIterator},
+    ops::{Index, IndexMut},
     thread,
     time::{Duration, Instant},
 };
@@ -20,6 +21,38 @@ pub struct RateLimitQueue<T> {
     queue: VecDeque<T>,
     allowance: usize,
     timepoint: Instant,
+    bound: Option<usize>,
+    size: usize,
+    size_of: Option<fn(&T) -> usize>,
+    created_at: Option<fn(&T) -> Instant>,
+    eviction_size_max: Option<usize>,
+    eviction_size_min: Option<usize>,
+    eviction_age_min: Option<Duration>,
+    mode: Mode,
+    tokens: f64,
+}
+
+/// The rate limiting algorithm used by [`try_dequeue`](RateLimitQueue::try_dequeue).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// `allowance` resets to `quantum` once a whole `interval` has elapsed.
+    /// Unused capacity is lost at each boundary. This is the default.
+    FixedWindow,
+    /// Tokens accrue continuously at `quantum` per `interval`, up to a cap of
+    /// `quantum`, giving a steady drip instead of quantum-sized bursts.
+    TokenBucket,
+}
+
+/// A type whose byte footprint drives size-based eviction.
+pub trait SizeOf {
+    /// Returns the size, in bytes, accounted for this element.
+    fn size_of(&self) -> usize;
+}
+
+/// A type stamped with a creation time, used for age-based eviction.
+pub trait CreatedAt {
+    /// Returns the instant at which this element was created.
+    fn created_at(&self) -> Instant;
 }
 
 /// A type that represents result of `try_dequeue()`.
@@ -102,9 +135,75 @@ impl<T> RateLimitQueue<T> {
             queue: VecDeque::with_capacity(cap),
             allowance: quantum,
             timepoint: Instant::now(),
+            bound: None,
+            size: 0,
+            size_of: None,
+            created_at: None,
+            eviction_size_max: None,
+            eviction_size_min: None,
+            eviction_age_min: None,
+            mode: Mode::FixedWindow,
+            tokens: quantum as f64,
         }
     }
 
+    /// Creates an empty queue that holds at most `max_len` elements.
+    ///
+    /// When the queue is full, [`enqueue`](RateLimitQueue::enqueue) and
+    /// [`extend`](RateLimitQueue::extend) drop the oldest elements from the
+    /// front, turning the queue into a lossy rate-limited buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use rate_limit_queue::RateLimitQueue;
+    ///
+    /// let mut queue = RateLimitQueue::with_bound(2, 100, Duration::from_secs(1));
+    /// queue.enqueue(1);
+    /// queue.enqueue(2);
+    /// assert_eq!(queue.enqueue(3), Some(1));
+    /// ```
+    #[inline]
+    pub fn with_bound(max_len: usize, quantum: usize, interval: Duration) -> RateLimitQueue<T> {
+        let mut queue = RateLimitQueue::with_capacity(max_len, quantum, interval);
+        queue.bound = Some(max_len);
+        queue
+    }
+
+    /// Creates a queue from an iterator, using the given `quantum` and
+    /// `interval`.
+    ///
+    /// This is the real builder behind the [`FromIterator`] impl, which has
+    /// nowhere to take a rate limit from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use rate_limit_queue::RateLimitQueue;
+    ///
+    /// let queue = RateLimitQueue::from_iter_with(2, Duration::from_secs(1), 0..5);
+    /// assert_eq!(queue.len(), 5);
+    /// ```
+    pub fn from_iter_with<I>(quantum: usize, interval: Duration, iter: I) -> RateLimitQueue<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut queue = RateLimitQueue::new(quantum, interval);
+        queue.extend(iter);
+        queue
+    }
+
+    /// Consumes the queue, yielding up to the current allowance from the front.
+    ///
+    /// Unlike [`into_iter`](RateLimitQueue::into_iter), this respects the rate
+    /// limit; the elements beyond the allowance are dropped with the queue.
+    pub fn drain_owned(self) -> impl Iterator<Item = T> {
+        let remaining = self.allowance.min(self.queue.len());
+        self.queue.into_iter().take(remaining)
+    }
+
     delegate! {
         target self.queue {
             /// Returns the number of elements the queue can hold without reallocating.
@@ -123,12 +222,73 @@ impl<T> RateLimitQueue<T> {
             pub fn reserve(&mut self, additional: usize);
             /// Shrinks the capacity of the queue as much as possible.
             pub fn shrink_to_fit(&mut self);
-            /// Shortens the queue, dropping excess elements from the back.
-            pub fn truncate(&mut self, len: usize);
             /// Returns the number of elements in the queue.
             pub fn len(&self) -> usize;
             /// Returns `true` if the queue is empty.
             pub fn is_empty(&self) -> bool;
+            /// Returns a reference to the front element, or `None` if empty.
+            pub fn front(&self) -> Option<&T>;
+            /// Returns a mutable reference to the front element, or `None` if empty.
+            pub fn front_mut(&mut self) -> Option<&mut T>;
+            /// Returns a reference to the back element, or `None` if empty.
+            pub fn back(&self) -> Option<&T>;
+            /// Returns a mutable reference to the back element, or `None` if empty.
+            pub fn back_mut(&mut self) -> Option<&mut T>;
+            /// Returns a reference to the element at `index`, or `None` if out of bounds.
+            pub fn get(&self, index: usize) -> Option<&T>;
+            /// Returns a mutable reference to the element at `index`, or `None` if out of bounds.
+            pub fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+            /// Returns the queue's contents as two slices in front-to-back order.
+            pub fn as_slices(&self) -> (&[T], &[T]);
+            /// Rearranges the internal storage so the whole queue is one slice.
+            pub fn make_contiguous(&mut self) -> &mut [T];
+        }
+    }
+
+    /// Shortens the queue, dropping excess elements from the back and keeping
+    /// the accounted size in sync.
+    pub fn truncate(&mut self, len: usize) {
+        if let Some(size_of) = self.size_of {
+            for value in self.queue.iter().skip(len) {
+                self.size -= size_of(value);
+            }
+        }
+
+        self.queue.truncate(len);
+    }
+
+    /// Removes all elements, resetting the accounted size and debiting the
+    /// allowance by the number of front items dropped.
+    pub fn clear(&mut self) {
+        self.allowance = self.allowance.saturating_sub(self.queue.len());
+        self.queue.clear();
+        self.size = 0;
+    }
+
+    /// Removes up to the current allowance from the front, debiting the
+    /// allowance as items are yielded.
+    ///
+    /// Dropping the returned iterator early leaves the remaining allowance and
+    /// the rest of the queue intact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use rate_limit_queue::RateLimitQueue;
+    ///
+    /// let mut queue = RateLimitQueue::new(2, Duration::from_secs(10));
+    /// queue.extend(0..5);
+    ///
+    /// let batch: Vec<_> = queue.drain().collect();
+    /// assert_eq!(batch, [0, 1]);
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        let remaining = self.allowance.min(self.queue.len());
+        Drain {
+            queue: self,
+            remaining,
         }
     }
 
@@ -142,8 +302,92 @@ impl<T> RateLimitQueue<T> {
         self.interval = interval;
     }
 
+    /// Changes the rate limiting mode, seeding the token bucket from the
+    /// current allowance so switching does not grant a fresh burst.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if mode == Mode::TokenBucket && self.mode != Mode::TokenBucket {
+            self.tokens = self.allowance as f64;
+            self.timepoint = Instant::now();
+        }
+
+        self.mode = mode;
+    }
+
+    /// Changes the maximum length, or removes it with `None`.
+    ///
+    /// Shrinking the bound below the current length immediately drops the
+    /// excess elements from the front.
+    pub fn set_bound(&mut self, bound: Option<usize>) {
+        self.bound = bound;
+
+        if let Some(max_len) = bound {
+            while self.queue.len() > max_len {
+                self.evict_front();
+            }
+        }
+    }
+
+    /// Removes the front element, keeping the running size counter in sync.
+    fn take_front(&mut self) -> Option<T> {
+        let value = self.queue.pop_front();
+
+        if let (Some(value), Some(size_of)) = (&value, self.size_of) {
+            self.size -= size_of(value);
+        }
+
+        value
+    }
+
+    /// Drops the front element, debiting `allowance` so that eviction never
+    /// lets the current interval hand out more than the limit allows.
+    fn evict_front(&mut self) -> Option<T> {
+        let value = self.take_front();
+
+        if value.is_some() {
+            self.allowance = self.allowance.saturating_sub(1);
+        }
+
+        value
+    }
+
+    /// Drops oldest elements while the accounted size exceeds the ceiling.
+    fn evict_by_size(&mut self) {
+        if let Some(max) = self.eviction_size_max {
+            while self.size > max && !self.queue.is_empty() {
+                self.evict_front();
+            }
+        }
+    }
+
+    /// Drops front elements older than `eviction_age_min`, but only while the
+    /// accounted size still exceeds `eviction_size_min`, so short bursts are
+    /// spared while a sustained backlog is trimmed.
+    fn evict_by_age(&mut self) {
+        let (age_min, size_min, created_at) =
+            match (self.eviction_age_min, self.eviction_size_min, self.created_at) {
+                (Some(age_min), Some(size_min), Some(created_at)) => {
+                    (age_min, size_min, created_at)
+                }
+                _ => return,
+            };
+
+        let now = Instant::now();
+
+        while self.size > size_min {
+            match self.queue.front() {
+                Some(front) if now.duration_since(created_at(front)) > age_min => {
+                    self.evict_front();
+                }
+                _ => break,
+            }
+        }
+    }
+
     /// Appends an element to the back of the queue.
     ///
+    /// If the queue is bounded and already full, the oldest element is dropped
+    /// from the front and returned.
+    ///
     /// # Examples
     ///
     /// ```
@@ -154,8 +398,22 @@ impl<T> RateLimitQueue<T> {
     /// queue.enqueue(1);
     /// queue.enqueue(2);
     /// ```
-    pub fn enqueue(&mut self, value: T) {
+    pub fn enqueue(&mut self, value: T) -> Option<T> {
+        if let Some(size_of) = self.size_of {
+            self.size += size_of(&value);
+        }
+
         self.queue.push_back(value);
+
+        let evicted = match self.bound {
+            Some(max_len) if self.queue.len() > max_len => self.evict_front(),
+            _ => None,
+        };
+
+        self.evict_by_size();
+        self.evict_by_age();
+
+        evicted
     }
 
     /// Removes the first element and returns it, or `None` if the queue is empty.
@@ -180,12 +438,19 @@ impl<T> RateLimitQueue<T> {
             DequeueResult::Data(value) => Some(value),
             DequeueResult::Empty => None,
             DequeueResult::Limit(rest) => {
-                thread::sleep(rest);
+                let mut rest = rest;
+
+                // The token-bucket wait is derived from floating-point math, so
+                // a single retry can round just short of a whole token; loop
+                // until one is actually available instead of assuming it.
+                loop {
+                    thread::sleep(rest);
 
-                if let DequeueResult::Data(value) = self.try_dequeue() {
-                    Some(value)
-                } else {
-                    unreachable!()
+                    match self.try_dequeue() {
+                        DequeueResult::Data(value) => return Some(value),
+                        DequeueResult::Empty => return None,
+                        DequeueResult::Limit(next) => rest = next,
+                    }
                 }
             }
         }
@@ -215,9 +480,18 @@ impl<T> RateLimitQueue<T> {
             return DequeueResult::Empty;
         }
 
+        match self.mode {
+            Mode::FixedWindow => self.try_dequeue_fixed(),
+            Mode::TokenBucket => self.try_dequeue_token(),
+        }
+    }
+
+    /// Strict fixed-window dequeue: the allowance resets to `quantum` only once
+    /// a whole interval has elapsed.
+    fn try_dequeue_fixed(&mut self) -> DequeueResult<T> {
         if self.allowance > 0 {
             self.allowance -= 1;
-            return self.queue.pop_front().into();
+            return self.take_front().into();
         }
 
         let now = Instant::now();
@@ -228,11 +502,39 @@ impl<T> RateLimitQueue<T> {
             None => {
                 self.allowance = self.quantum - 1;
                 self.timepoint = now;
-                self.queue.pop_front().into()
+                self.take_front().into()
             }
         }
     }
 
+    /// Token-bucket dequeue: credits tokens for the elapsed time (capped at
+    /// `quantum`) and hands out one item per whole token.
+    fn try_dequeue_token(&mut self) -> DequeueResult<T> {
+        if self.quantum == 0 {
+            // No tokens ever accrue, so the queue can never drain. Report the
+            // full interval as the wait rather than dividing by zero.
+            return DequeueResult::Limit(self.interval);
+        }
+
+        let quantum = self.quantum as f64;
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.timepoint);
+
+        let credit = elapsed.as_secs_f64() / self.interval.as_secs_f64() * quantum;
+        self.tokens = (self.tokens + credit).min(quantum);
+        self.timepoint = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.allowance = self.tokens as usize;
+            self.take_front().into()
+        } else {
+            let per_token = self.interval.as_secs_f64() / quantum;
+            let rest = Duration::from_secs_f64((1.0 - self.tokens) * per_token);
+            DequeueResult::Limit(rest)
+        }
+    }
+
     /// Returns a front-to-back iterator.
     ///
     /// # Examples
@@ -274,8 +576,133 @@ impl<T> RateLimitQueue<T> {
     }
 }
 
+impl<T: SizeOf> RateLimitQueue<T> {
+    /// Wires up size accounting the first time a size knob is set, seeding the
+    /// running counter from the elements already present.
+    fn enable_size_tracking(&mut self) {
+        if self.size_of.is_none() {
+            self.size_of = Some(T::size_of);
+            self.size = self.queue.iter().map(T::size_of).sum();
+        }
+    }
+
+    /// Sets the size ceiling above which oldest elements are evicted, or
+    /// removes it with `None`.
+    pub fn set_eviction_size_max(&mut self, size: Option<usize>) {
+        self.enable_size_tracking();
+        self.eviction_size_max = size;
+        self.evict_by_size();
+    }
+
+    /// Sets the size floor below which age-based eviction stops, or removes it
+    /// with `None`.
+    pub fn set_eviction_size_min(&mut self, size: Option<usize>) {
+        self.enable_size_tracking();
+        self.eviction_size_min = size;
+    }
+}
+
+impl<T: SizeOf + CreatedAt> RateLimitQueue<T> {
+    /// Sets the minimum age a front element must reach before it can be evicted
+    /// while the backlog exceeds `eviction_size_min`, or removes it with `None`.
+    pub fn set_eviction_age_min(&mut self, age: Option<Duration>) {
+        self.enable_size_tracking();
+
+        if self.created_at.is_none() {
+            self.created_at = Some(T::created_at);
+        }
+
+        self.eviction_age_min = age;
+        self.evict_by_age();
+    }
+}
+
+impl<T: PartialEq> RateLimitQueue<T> {
+    /// Returns `true` if the queue contains an element equal to `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.queue.contains(value)
+    }
+}
+
+impl<T> Index<usize> for RateLimitQueue<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.queue[index]
+    }
+}
+
+impl<T> IndexMut<usize> for RateLimitQueue<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.queue[index]
+    }
+}
+
+/// A draining iterator over the currently-permitted front elements.
+///
+/// Created by [`RateLimitQueue::drain`].
+pub struct Drain<'a, T> {
+    queue: &'a mut RateLimitQueue<T>,
+    remaining: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        self.queue.allowance -= 1;
+        self.queue.take_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> FromIterator<T> for RateLimitQueue<T> {
+    /// Collects into an unbounded queue with an effectively unlimited
+    /// allowance. Use [`from_iter_with`](RateLimitQueue::from_iter_with) to set
+    /// a real `quantum`/`interval`.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> RateLimitQueue<T> {
+        RateLimitQueue::from_iter_with(usize::MAX, Duration::from_secs(0), iter)
+    }
+}
+
+impl<T> IntoIterator for RateLimitQueue<T> {
+    type Item = T;
+    type IntoIter = std::collections::vec_deque::IntoIter<T>;
+
+    /// Yields the owned elements front-to-back, ignoring the rate limit (for
+    /// draining on shutdown).
+    fn into_iter(self) -> Self::IntoIter {
+        self.queue.into_iter()
+    }
+}
+
 impl<T> Extend<T> for RateLimitQueue<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.queue.extend(iter)
+        for value in iter {
+            if let Some(size_of) = self.size_of {
+                self.size += size_of(&value);
+            }
+
+            self.queue.push_back(value);
+        }
+
+        if let Some(max_len) = self.bound {
+            while self.queue.len() > max_len {
+                self.evict_front();
+            }
+        }
+
+        self.evict_by_size();
+        self.evict_by_age();
     }
 }
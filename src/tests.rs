@@ -51,6 +51,127 @@ fn it_should_not_have_accumulative_effect() {
     assert_eq!(queue.try_dequeue(), DequeueResult::Empty);
 }
 
+#[test]
+fn it_should_evict_from_front_when_bounded() {
+    let quantum = 2;
+    let interval = Duration::from_millis(100);
+
+    let mut queue = RateLimitQueue::with_bound(5, quantum, interval);
+
+    for i in 0..5 {
+        assert_eq!(queue.enqueue(i), None);
+    }
+
+    // The queue is full, so the two oldest elements are dropped and the
+    // eviction debits `allowance` down to zero.
+    assert_eq!(queue.enqueue(5), Some(0));
+    assert_eq!(queue.enqueue(6), Some(1));
+
+    let actual: Vec<&u32> = queue.iter().collect();
+    assert!(actual.is_empty());
+
+    assert!(queue.try_dequeue().is_limit());
+}
+
+#[test]
+fn it_should_evict_by_size() {
+    struct Blob(usize);
+
+    impl SizeOf for Blob {
+        fn size_of(&self) -> usize {
+            self.0
+        }
+    }
+
+    let quantum = 10;
+    let interval = Duration::from_millis(100);
+
+    let mut queue = RateLimitQueue::new(quantum, interval);
+    queue.set_eviction_size_max(Some(5));
+
+    queue.enqueue(Blob(2));
+    queue.enqueue(Blob(2));
+    // Total would be 6 > 5, so the oldest element is dropped back to 4.
+    queue.enqueue(Blob(2));
+
+    assert_eq!(queue.len(), 2);
+}
+
+#[test]
+fn it_should_drain_up_to_allowance() {
+    let quantum = 2;
+    let interval = Duration::from_millis(100);
+
+    let mut queue = RateLimitQueue::new(quantum, interval);
+    queue.extend(0..5);
+
+    let batch: Vec<_> = queue.drain().collect();
+    assert_eq!(batch, [0, 1]);
+    assert_eq!(queue.len(), 3);
+
+    // The allowance was fully spent by the drain.
+    assert!(queue.try_dequeue().is_limit());
+}
+
+#[test]
+fn it_should_keep_allowance_when_drain_dropped_early() {
+    let quantum = 3;
+    let interval = Duration::from_millis(100);
+
+    let mut queue = RateLimitQueue::new(quantum, interval);
+    queue.extend(0..5);
+
+    {
+        let mut drain = queue.drain();
+        assert_eq!(drain.next(), Some(0));
+    }
+
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.try_dequeue(), DequeueResult::Data(1));
+    assert_eq!(queue.try_dequeue(), DequeueResult::Data(2));
+    assert!(queue.try_dequeue().is_limit());
+}
+
+#[test]
+fn it_should_collect_and_iterate() {
+    let quantum = 2;
+    let interval = Duration::from_millis(100);
+
+    let queue = RateLimitQueue::from_iter_with(quantum, interval, 0..3);
+    assert_eq!(queue.drain_owned().collect::<Vec<_>>(), [0, 1]);
+
+    let queue = RateLimitQueue::from_iter_with(quantum, interval, 0..3);
+    assert_eq!(queue.into_iter().collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn it_should_drip_in_token_bucket_mode() {
+    let quantum = 10;
+    let interval = Duration::from_millis(100);
+
+    let mut queue = RateLimitQueue::new(quantum, interval);
+    queue.extend(0..2 * quantum);
+
+    // Spend the initial full bucket.
+    for i in 0..quantum {
+        assert_eq!(queue.try_dequeue(), DequeueResult::Data(i));
+    }
+
+    queue.set_mode(Mode::TokenBucket);
+
+    // Bucket was seeded from the (now zero) allowance, so we must wait.
+    assert!(queue.try_dequeue().is_limit());
+
+    // After roughly one interval the bucket refills to its cap.
+    thread::sleep(interval + interval / 10);
+
+    for i in quantum..2 * quantum {
+        assert_eq!(queue.try_dequeue(), DequeueResult::Data(i));
+    }
+
+    assert_eq!(queue.try_dequeue(), DequeueResult::Empty);
+}
+
 #[test]
 fn it_should_change_allowance_during_iter() {
     let quantum = 2;